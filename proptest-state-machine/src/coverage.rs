@@ -0,0 +1,163 @@
+//-
+// Copyright 2023 The proptest developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Coverage-guided transition weighting.
+//!
+//! [`ReferenceStateMachine::transitions`](crate::ReferenceStateMachine::transitions)
+//! draws from a fixed, static distribution, so states and edges that are
+//! individually unlikely - but not actually excluded by `preconditions` -
+//! can stay unexplored for an entire run. Wiring a [`CoverageTracker`] in
+//! with [`Sequential::with_coverage_feedback`](crate::Sequential::with_coverage_feedback)
+//! counteracts that: instead of committing to the first sampled transition,
+//! generation draws a handful of candidates and asks the tracker to pick
+//! whichever one least increases confidence that this edge (or the state it
+//! leads to) has already been covered.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+/// An observer consulted by [`Sequential`](crate::Sequential) while
+/// generating transitions, used to bias exploration toward under-covered
+/// `(state, transition)` edges. Wire one in with
+/// [`Sequential::with_coverage_feedback`](crate::Sequential::with_coverage_feedback).
+pub trait CoverageFeedback<State, Transition> {
+    /// `state` is the initial state generation starts from.
+    fn note_initial(&mut self, state: &State);
+
+    /// Choose which of `candidates` to commit to next from `state`. Each
+    /// candidate pairs a sampled transition with the state applying it
+    /// would produce, so the choice can account for both how often the
+    /// edge itself has been taken and whether the resulting state has been
+    /// seen before. `candidates` is never empty.
+    fn pick(&mut self, state: &State, candidates: &[(Transition, State)]) -> usize;
+
+    /// `transition` was actually taken from `state`, landing on `to`.
+    fn record(&mut self, state: &State, transition: &Transition, to: &State);
+}
+
+/// A [`CoverageFeedback`] that counts how often each `(state, transition)`
+/// edge - abstracted down to a hashable `CoverageKey`/`TransitionKey` pair
+/// via a pair of key functions, the same way [`crate::KeyedRecorder`] adapts
+/// a [`crate::TransitionGraph`] - has been taken so far, and picks whichever
+/// candidate edge leads to a never-before-seen abstract state, falling back
+/// to the least-taken edge otherwise.
+pub struct CoverageTracker<State, Transition, CoverageKey, TransitionKey> {
+    counts: HashMap<(CoverageKey, TransitionKey), usize>,
+    seen_states: HashSet<CoverageKey>,
+    state_key: fn(&State) -> CoverageKey,
+    transition_key: fn(&Transition) -> TransitionKey,
+}
+
+impl<State, Transition, CoverageKey, TransitionKey>
+    CoverageTracker<State, Transition, CoverageKey, TransitionKey>
+{
+    /// Create an empty tracker that abstracts states and transitions with
+    /// `state_key`/`transition_key`.
+    pub fn new(
+        state_key: fn(&State) -> CoverageKey,
+        transition_key: fn(&Transition) -> TransitionKey,
+    ) -> Self {
+        Self {
+            counts: HashMap::new(),
+            seen_states: HashSet::new(),
+            state_key,
+            transition_key,
+        }
+    }
+}
+
+impl<
+        State,
+        Transition,
+        CoverageKey: Clone + Eq + Hash,
+        TransitionKey: Clone + Eq + Hash,
+    > CoverageFeedback<State, Transition>
+    for CoverageTracker<State, Transition, CoverageKey, TransitionKey>
+{
+    fn note_initial(&mut self, state: &State) {
+        self.seen_states.insert((self.state_key)(state));
+    }
+
+    fn pick(&mut self, state: &State, candidates: &[(Transition, State)]) -> usize {
+        let from = (self.state_key)(state);
+        candidates
+            .iter()
+            .map(|(transition, to)| {
+                if !self.seen_states.contains(&(self.state_key)(to)) {
+                    // Never-before-seen states always win, ahead of any
+                    // edge count.
+                    0
+                } else {
+                    self.counts
+                        .get(&(from.clone(), (self.transition_key)(transition)))
+                        .copied()
+                        .unwrap_or(0)
+                        + 1
+                }
+            })
+            .enumerate()
+            .min_by_key(|&(_, score)| score)
+            .map(|(ix, _)| ix)
+            .unwrap_or(0)
+    }
+
+    fn record(&mut self, state: &State, transition: &Transition, to: &State) {
+        let key = ((self.state_key)(state), (self.transition_key)(transition));
+        *self.counts.entry(key).or_insert(0) += 1;
+        self.seen_states.insert((self.state_key)(to));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tracker() -> CoverageTracker<i32, &'static str, i32, &'static str> {
+        CoverageTracker::new(|state| *state, |transition| *transition)
+    }
+
+    #[test]
+    fn pick_prefers_a_never_before_seen_state() {
+        let mut tracker = tracker();
+        tracker.note_initial(&0);
+
+        let candidates = [("incr", 1), ("noop", 0)];
+        assert_eq!(tracker.pick(&0, &candidates), 0);
+    }
+
+    #[test]
+    fn pick_falls_back_to_the_least_taken_edge_once_all_states_are_seen() {
+        let mut tracker = tracker();
+        tracker.note_initial(&0);
+        tracker.record(&0, &"a", &1);
+        tracker.record(&0, &"a", &1);
+        tracker.record(&0, &"b", &1);
+        tracker.seen_states.insert(1);
+
+        let candidates = [("a", 1), ("b", 1)];
+        assert_eq!(tracker.pick(&0, &candidates), 1);
+    }
+
+    #[test]
+    fn record_updates_counts_and_seen_states() {
+        let mut tracker = tracker();
+        tracker.note_initial(&0);
+        tracker.record(&0, &"a", &1);
+
+        assert_eq!(
+            tracker
+                .counts
+                .get(&(0, "a"))
+                .copied()
+                .unwrap_or(0),
+            1
+        );
+        assert!(tracker.seen_states.contains(&1));
+    }
+}
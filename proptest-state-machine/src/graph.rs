@@ -0,0 +1,258 @@
+//-
+// Copyright 2023 The proptest developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Graphviz/DOT export of the reference state machine transitions a test
+//! actually explored.
+//!
+//! [`State`](crate::ReferenceStateMachine::State) need not be `Hash`, so a
+//! [`TransitionGraph`] is keyed by a caller-chosen abstraction of it
+//! instead (e.g. a coarser summary, or the state itself when it happens to
+//! be `Hash`). Wire one into [`Sequential`](crate::Sequential) with
+//! [`Sequential::with_recorder`](crate::Sequential::with_recorder) - wrapped
+//! in a [`KeyedRecorder`] to supply the key functions - to have every
+//! transition observed during generation and shrinking recorded
+//! automatically, then call [`TransitionGraph::to_dot`] to render
+//! everything that was covered.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::Hash;
+
+/// Records every `(pre_state, transition, post_state)` triple observed while
+/// running a reference state machine, keyed by caller-supplied `StateKey`
+/// and `TransitionLabel` abstractions, and renders them as a Graphviz DOT
+/// digraph.
+#[derive(Clone, Debug)]
+pub struct TransitionGraph<StateKey, TransitionLabel> {
+    /// States reached as an `init_state`.
+    initial_states: HashSet<StateKey>,
+    /// `from -> label -> { to }`: every transition taken, and the state(s)
+    /// it led to.
+    edges: HashMap<StateKey, HashMap<TransitionLabel, HashSet<StateKey>>>,
+    /// `from -> { label }`: transitions that were attempted from `from` but
+    /// rejected by `preconditions`.
+    rejected: HashMap<StateKey, HashSet<TransitionLabel>>,
+}
+
+impl<StateKey, TransitionLabel> Default
+    for TransitionGraph<StateKey, TransitionLabel>
+{
+    fn default() -> Self {
+        Self {
+            initial_states: HashSet::new(),
+            edges: HashMap::new(),
+            rejected: HashMap::new(),
+        }
+    }
+}
+
+impl<StateKey: Clone + Eq + Hash, TransitionLabel: Clone + Eq + Hash>
+    TransitionGraph<StateKey, TransitionLabel>
+{
+    /// Create an empty graph.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `state` was seen as an initial state.
+    pub fn record_initial(&mut self, state: StateKey) {
+        self.initial_states.insert(state);
+    }
+
+    /// Record that applying `label` from `from` was accepted by
+    /// `preconditions` and led to `to`.
+    pub fn record_transition(
+        &mut self,
+        from: StateKey,
+        label: TransitionLabel,
+        to: StateKey,
+    ) {
+        self.edges
+            .entry(from)
+            .or_default()
+            .entry(label)
+            .or_default()
+            .insert(to);
+    }
+
+    /// Record that applying `label` from `from` was rejected by
+    /// `preconditions`.
+    pub fn record_rejected(&mut self, from: StateKey, label: TransitionLabel) {
+        self.rejected.entry(from).or_default().insert(label);
+    }
+}
+
+impl<
+        StateKey: Clone + Eq + Hash + fmt::Display,
+        TransitionLabel: Clone + Eq + Hash + fmt::Display,
+    > TransitionGraph<StateKey, TransitionLabel>
+{
+    /// Render everything recorded so far as a Graphviz DOT digraph. Initial
+    /// states are filled in light blue; states with at least one rejected
+    /// transition get a dashed red self-loop labeled with what was
+    /// rejected, so gated edges are visible alongside the ones that were
+    /// actually taken.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph state_machine {\n");
+
+        for state in &self.initial_states {
+            dot.push_str(&format!(
+                "    {:?} [style=filled, fillcolor=lightblue];\n",
+                state.to_string()
+            ));
+        }
+
+        for (from, labels) in &self.edges {
+            for (label, tos) in labels {
+                for to in tos {
+                    dot.push_str(&format!(
+                        "    {:?} -> {:?} [label={:?}];\n",
+                        from.to_string(),
+                        to.to_string(),
+                        label.to_string()
+                    ));
+                }
+            }
+        }
+
+        for (from, labels) in &self.rejected {
+            for label in labels {
+                dot.push_str(&format!(
+                    "    {:?} -> {:?} [label={:?}, style=dashed, color=red];\n",
+                    from.to_string(),
+                    from.to_string(),
+                    format!("reject: {label}")
+                ));
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// An observer that [`Sequential`](crate::Sequential) (and its
+/// [`SequentialValueTree`](crate::SequentialValueTree)) notifies of every
+/// transition it considers, during both generation and shrinking. Wire one
+/// in with
+/// [`Sequential::with_recorder`](crate::Sequential::with_recorder).
+pub trait TransitionRecorder<State, Transition> {
+    /// `state` was used as an initial state.
+    fn record_initial(&mut self, state: &State);
+    /// `transition` was applied from `from`, landing on `to`.
+    fn record_transition(
+        &mut self,
+        from: &State,
+        transition: &Transition,
+        to: &State,
+    );
+    /// `transition` was rejected by `preconditions` from `from`.
+    fn record_rejected(&mut self, from: &State, transition: &Transition);
+}
+
+/// Adapts a [`TransitionGraph`] into a [`TransitionRecorder`] by mapping
+/// `State`/`Transition` down to the graph's `StateKey`/`TransitionLabel`
+/// with a pair of key functions, the same way the rest of this crate
+/// threads user behavior through plain `fn` pointers.
+pub struct KeyedRecorder<State, Transition, StateKey, TransitionLabel> {
+    /// The underlying graph being built up.
+    pub graph: TransitionGraph<StateKey, TransitionLabel>,
+    state_key: fn(&State) -> StateKey,
+    transition_label: fn(&Transition) -> TransitionLabel,
+}
+
+impl<State, Transition, StateKey, TransitionLabel>
+    KeyedRecorder<State, Transition, StateKey, TransitionLabel>
+where
+    StateKey: Clone + Eq + Hash,
+    TransitionLabel: Clone + Eq + Hash,
+{
+    /// Create an empty recorder that keys states and transitions with
+    /// `state_key`/`transition_label`.
+    pub fn new(
+        state_key: fn(&State) -> StateKey,
+        transition_label: fn(&Transition) -> TransitionLabel,
+    ) -> Self {
+        Self {
+            graph: TransitionGraph::new(),
+            state_key,
+            transition_label,
+        }
+    }
+}
+
+impl<State, Transition, StateKey, TransitionLabel>
+    TransitionRecorder<State, Transition>
+    for KeyedRecorder<State, Transition, StateKey, TransitionLabel>
+where
+    StateKey: Clone + Eq + Hash,
+    TransitionLabel: Clone + Eq + Hash,
+{
+    fn record_initial(&mut self, state: &State) {
+        self.graph.record_initial((self.state_key)(state));
+    }
+
+    fn record_transition(
+        &mut self,
+        from: &State,
+        transition: &Transition,
+        to: &State,
+    ) {
+        self.graph.record_transition(
+            (self.state_key)(from),
+            (self.transition_label)(transition),
+            (self.state_key)(to),
+        );
+    }
+
+    fn record_rejected(&mut self, from: &State, transition: &Transition) {
+        self.graph.record_rejected(
+            (self.state_key)(from),
+            (self.transition_label)(transition),
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn to_dot_renders_initial_states_edges_and_rejections() {
+        let mut graph: TransitionGraph<i32, &str> = TransitionGraph::new();
+        graph.record_initial(0);
+        graph.record_transition(0, "incr", 1);
+        graph.record_rejected(0, "decr");
+
+        let dot = graph.to_dot();
+
+        assert!(dot.starts_with("digraph state_machine {\n"));
+        assert!(dot.ends_with("}\n"));
+        assert!(dot.contains("\"0\" [style=filled, fillcolor=lightblue];"));
+        assert!(dot.contains("\"0\" -> \"1\" [label=\"incr\"];"));
+        assert!(dot
+            .contains("\"0\" -> \"0\" [label=\"reject: decr\", style=dashed, color=red];"));
+    }
+
+    #[test]
+    fn keyed_recorder_adapts_into_the_underlying_graph() {
+        let mut recorder: KeyedRecorder<i32, &str, i32, &str> =
+            KeyedRecorder::new(|state| *state, |transition| *transition);
+
+        recorder.record_initial(&0);
+        recorder.record_transition(&0, &"incr", &1);
+        recorder.record_rejected(&1, &"decr");
+
+        let dot = recorder.graph.to_dot();
+        assert!(dot.contains("\"0\" [style=filled, fillcolor=lightblue];"));
+        assert!(dot.contains("\"0\" -> \"1\" [label=\"incr\"];"));
+        assert!(dot
+            .contains("\"1\" -> \"1\" [label=\"reject: decr\", style=dashed, color=red];"));
+    }
+}
@@ -0,0 +1,28 @@
+//-
+// Copyright 2023 The proptest developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! State machine based testing support for proptest.
+//!
+//! See [`ReferenceStateMachine`] for the entry point into this crate.
+
+mod coverage;
+mod graph;
+mod nested;
+mod parallel;
+mod strategy;
+#[cfg(feature = "serde")]
+mod trace;
+
+pub use coverage::*;
+pub use graph::*;
+pub use nested::*;
+pub use parallel::*;
+pub use strategy::*;
+#[cfg(feature = "serde")]
+pub use trace::*;
@@ -0,0 +1,527 @@
+//-
+// Copyright 2023 The proptest developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Hierarchical / nested reference state machines.
+//!
+//! Modeling a layered protocol (e.g. session -> request -> frame) as a
+//! single [`ReferenceStateMachine`] tends to flatten every layer into one
+//! giant `Transition` enum, with preconditions that have to re-derive which
+//! layer is "current" every time. [`NestedStateMachine`] lets a parent
+//! instead embed a child [`ReferenceStateMachine`] wholesale: a parent
+//! transition can enter the child, which then runs its own
+//! `transitions`/`apply`/`preconditions` until it signals
+//! [`Complete::Done`], at which point its final state is folded back into
+//! the parent and generation resumes from the parent's own transitions.
+//! [`Nested<P>`] turns such a `P: NestedStateMachine` into an ordinary
+//! [`ReferenceStateMachine`], so it plugs into
+//! [`ReferenceStateMachine::sequential_strategy`] /
+//! [`ReferenceStateMachine::parallel_strategy`] like any other.
+
+use std::marker::PhantomData;
+
+use proptest::std_facade::fmt::{Debug, Formatter, Result};
+use proptest::strategy::{BoxedStrategy, Strategy};
+
+use crate::ReferenceStateMachine;
+
+/// The signal a child sub-machine embedded via [`NestedStateMachine`] gives
+/// back to the parent after each of its transitions is applied.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Complete {
+    /// The child sub-run is still in progress; keep generating transitions
+    /// from [`NestedStateMachine::Child`].
+    Running,
+    /// The child sub-run is finished. Its final state is folded back into
+    /// the parent with [`NestedStateMachine::exit_child`], and generation
+    /// resumes from the parent's own transitions.
+    Done,
+}
+
+/// Describes how a parent state machine embeds a child
+/// [`ReferenceStateMachine`], so [`Nested<Self>`] can combine the two into a
+/// single [`ReferenceStateMachine`] without flattening both layers into one
+/// transition enum.
+///
+/// Implement this the same way you'd implement [`ReferenceStateMachine`]
+/// directly for the parent's own behavior, plus [`NestedStateMachine::enter_child`],
+/// [`NestedStateMachine::child_complete`] and [`NestedStateMachine::exit_child`]
+/// to describe when a child sub-run starts, when it's done, and how it
+/// reports back.
+pub trait NestedStateMachine {
+    /// The parent's own state, excluding any active child sub-run.
+    type State: Clone + Debug;
+
+    /// The parent's own transitions, excluding the child's.
+    type Transition: Clone + Debug;
+
+    /// The embedded child reference state machine.
+    type Child: ReferenceStateMachine;
+
+    /// The parent's initial state may be generated by any strategy.
+    fn init_state() -> BoxedStrategy<Self::State>;
+
+    /// Generate the parent's own transitions. Only consulted while no child
+    /// sub-run is active.
+    fn transitions(state: &Self::State) -> BoxedStrategy<Self::Transition>;
+
+    /// Apply one of the parent's own transitions.
+    fn apply(state: Self::State, transition: &Self::Transition) -> Self::State;
+
+    /// Pre-conditions for the parent's own transitions. Only consulted
+    /// while no child sub-run is active. Defaults to allowing any
+    /// transition.
+    fn preconditions(
+        state: &Self::State,
+        transition: &Self::Transition,
+    ) -> bool {
+        let _ = (state, transition);
+
+        true
+    }
+
+    /// An optional fallback transition strategy for the parent's own
+    /// transitions, with the same contract as
+    /// [`ReferenceStateMachine::fallback`]. Only consulted while no child
+    /// sub-run is active; the child's own `fallback` is used while one is.
+    /// Defaults to no fallback.
+    fn fallback(state: &Self::State) -> Option<BoxedStrategy<Self::Transition>> {
+        let _ = state;
+
+        None
+    }
+
+    /// Does applying `transition` from `state` enter a child sub-run? If
+    /// so, return the child's initial state; [`Nested`] will generate
+    /// [`NestedStateMachine::Child`]'s transitions from then on, until it
+    /// reports [`Complete::Done`]. Defaults to never entering a child.
+    fn enter_child(
+        state: &Self::State,
+        transition: &Self::Transition,
+    ) -> Option<<Self::Child as ReferenceStateMachine>::State> {
+        let _ = (state, transition);
+
+        None
+    }
+
+    /// Has the child, now in `child_state`, signalled it's done? Checked
+    /// after every child transition is applied.
+    fn child_complete(
+        child_state: &<Self::Child as ReferenceStateMachine>::State,
+    ) -> Complete;
+
+    /// Fold the child's final state back into the parent once it signals
+    /// [`Complete::Done`].
+    fn exit_child(
+        state: Self::State,
+        child_state: <Self::Child as ReferenceStateMachine>::State,
+    ) -> Self::State;
+}
+
+/// The combined state of a [`NestedStateMachine`]: the parent's own state,
+/// plus the active child sub-run's state, if any.
+pub struct NestedState<P: NestedStateMachine> {
+    /// The parent's own state.
+    pub parent: P::State,
+    /// The active child sub-run's state, if a transition has entered one
+    /// and it hasn't yet signalled [`Complete::Done`].
+    pub child: Option<<P::Child as ReferenceStateMachine>::State>,
+}
+
+// Derived manually, since `#[derive(Clone, Debug)]` would require `P`
+// itself (a marker type, not a field) to be `Clone`/`Debug`.
+impl<P: NestedStateMachine> Clone for NestedState<P> {
+    fn clone(&self) -> Self {
+        Self {
+            parent: self.parent.clone(),
+            child: self.child.clone(),
+        }
+    }
+}
+
+impl<P: NestedStateMachine> Debug for NestedState<P> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        f.debug_struct("NestedState")
+            .field("parent", &self.parent)
+            .field("child", &self.child)
+            .finish()
+    }
+}
+
+/// The combined transition of a [`NestedStateMachine`]: either one of the
+/// parent's own transitions, or one of the currently active child's.
+pub enum NestedTransition<P: NestedStateMachine> {
+    /// One of the parent's own transitions.
+    Parent(P::Transition),
+    /// One of the currently active child's transitions.
+    Child(<P::Child as ReferenceStateMachine>::Transition),
+}
+
+// Derived manually, for the same reason as `NestedState`'s impls above.
+impl<P: NestedStateMachine> Clone for NestedTransition<P> {
+    fn clone(&self) -> Self {
+        match self {
+            NestedTransition::Parent(transition) => {
+                NestedTransition::Parent(transition.clone())
+            }
+            NestedTransition::Child(transition) => {
+                NestedTransition::Child(transition.clone())
+            }
+        }
+    }
+}
+
+impl<P: NestedStateMachine> Debug for NestedTransition<P> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match self {
+            NestedTransition::Parent(transition) => {
+                f.debug_tuple("Parent").field(transition).finish()
+            }
+            NestedTransition::Child(transition) => {
+                f.debug_tuple("Child").field(transition).finish()
+            }
+        }
+    }
+}
+
+/// Combines a [`NestedStateMachine`]'s parent and child into a single
+/// [`ReferenceStateMachine`]: generates parent transitions while no child
+/// sub-run is active, and child transitions - gated by the child's own
+/// `preconditions` - while one is, folding the child's final state back
+/// into the parent once it signals [`Complete::Done`].
+///
+/// Because the flattened transition sequence places an entire child
+/// sub-run in a contiguous range, [`crate::Sequential`]'s existing
+/// ddmin-style chunked deletion already tends to remove a whole sub-run in
+/// one shrink, the same way it collapses any other irrelevant contiguous
+/// range; no separate shrinking logic is needed to treat it as a unit.
+pub struct Nested<P>(PhantomData<P>);
+
+impl<P: NestedStateMachine + 'static> ReferenceStateMachine for Nested<P> {
+    type State = NestedState<P>;
+    type Transition = NestedTransition<P>;
+
+    fn init_state() -> BoxedStrategy<Self::State> {
+        P::init_state()
+            .prop_map(|parent| NestedState {
+                parent,
+                child: None,
+            })
+            .boxed()
+    }
+
+    fn transitions(state: &Self::State) -> BoxedStrategy<Self::Transition> {
+        match &state.child {
+            Some(child_state) => P::Child::transitions(child_state)
+                .prop_map(NestedTransition::Child)
+                .boxed(),
+            None => P::transitions(&state.parent)
+                .prop_map(NestedTransition::Parent)
+                .boxed(),
+        }
+    }
+
+    fn apply(state: Self::State, transition: &Self::Transition) -> Self::State {
+        match (state.child, transition) {
+            (None, NestedTransition::Parent(transition)) => {
+                let parent = P::apply(state.parent, transition);
+                let child = P::enter_child(&parent, transition);
+                NestedState { parent, child }
+            }
+            (Some(child_state), NestedTransition::Child(transition)) => {
+                let child_state = P::Child::apply(child_state, transition);
+                if P::child_complete(&child_state) == Complete::Done {
+                    let parent = P::exit_child(state.parent, child_state);
+                    NestedState {
+                        parent,
+                        child: None,
+                    }
+                } else {
+                    NestedState {
+                        parent: state.parent,
+                        child: Some(child_state),
+                    }
+                }
+            }
+            // `transitions` never generates a parent transition while a
+            // child is active, or vice versa; leave the state untouched
+            // rather than panic if one is replayed anyway (e.g. from a
+            // hand-written regression trace).
+            (child, _) => NestedState {
+                parent: state.parent,
+                child,
+            },
+        }
+    }
+
+    fn preconditions(state: &Self::State, transition: &Self::Transition) -> bool {
+        match (&state.child, transition) {
+            (None, NestedTransition::Parent(transition)) => {
+                P::preconditions(&state.parent, transition)
+            }
+            (Some(child_state), NestedTransition::Child(transition)) => {
+                P::Child::preconditions(child_state, transition)
+            }
+            _ => false,
+        }
+    }
+
+    fn fallback(state: &Self::State) -> Option<BoxedStrategy<Self::Transition>> {
+        match &state.child {
+            Some(child_state) => P::Child::fallback(child_state)
+                .map(|strategy| strategy.prop_map(NestedTransition::Child).boxed()),
+            None => P::fallback(&state.parent)
+                .map(|strategy| strategy.prop_map(NestedTransition::Parent).boxed()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use proptest::strategy::{Strategy, ValueTree};
+    use proptest::test_runner::TestRunner;
+
+    use crate::SequentialValueTree;
+    use session_state_machine::*;
+
+    /// How many parent `StartDoor`s worth of transitions to generate: each
+    /// one contributes 1 parent transition plus 2 child `Push`es.
+    const SESSIONS: usize = 3;
+    const TRANSITIONS: usize = SESSIONS * 3;
+
+    type TestValueTree = SequentialValueTree<
+        NestedState<SessionMachine>,
+        NestedTransition<SessionMachine>,
+        <BoxedStrategy<NestedState<SessionMachine>> as Strategy>::Tree,
+        <BoxedStrategy<NestedTransition<SessionMachine>> as Strategy>::Tree,
+    >;
+
+    fn deterministic_nested_value_tree() -> TestValueTree {
+        let sequential =
+            <Nested<SessionMachine> as ReferenceStateMachine>::sequential_strategy(
+                TRANSITIONS,
+            );
+        let mut runner = TestRunner::deterministic();
+        sequential.new_tree(&mut runner).unwrap()
+    }
+
+    /// Replay `value_tree`'s current transitions from its initial state,
+    /// asserting every one still satisfies `preconditions` - in particular
+    /// that a `Child` transition never appears without a preceding `Parent`
+    /// transition that actually entered a child sub-run.
+    fn check_preconditions(value_tree: &TestValueTree) {
+        let (mut state, transitions) = value_tree.current();
+        for transition in transitions {
+            assert!(<Nested<SessionMachine> as ReferenceStateMachine>::preconditions(
+                &state, &transition
+            ));
+            state = <Nested<SessionMachine> as ReferenceStateMachine>::apply(
+                state, &transition,
+            );
+        }
+    }
+
+    #[test]
+    fn nested_sequential_value_tree_shrinks_preserve_preconditions() {
+        let mut value_tree = deterministic_nested_value_tree();
+        check_preconditions(&value_tree);
+
+        let mut simplifications = 0;
+        while value_tree.simplify() {
+            check_preconditions(&value_tree);
+            simplifications += 1;
+        }
+        assert!(simplifications > 0);
+    }
+
+    #[test]
+    fn nested_apply_enters_child_then_folds_it_back_once_done() {
+        let state = NestedState::<SessionMachine> {
+            parent: 0,
+            child: None,
+        };
+
+        let state = <Nested<SessionMachine> as ReferenceStateMachine>::apply(
+            state,
+            &NestedTransition::Parent(SessionTransition::StartDoor),
+        );
+        assert_eq!(state.parent, 0);
+        assert_eq!(state.child, Some(0));
+
+        let state = <Nested<SessionMachine> as ReferenceStateMachine>::apply(
+            state,
+            &NestedTransition::Child(DoorTransition::Push),
+        );
+        assert_eq!(state.child, Some(1));
+
+        // The second `Push` makes `child_complete` report `Done`, folding
+        // the child's final state back into the parent via `exit_child`.
+        let state = <Nested<SessionMachine> as ReferenceStateMachine>::apply(
+            state,
+            &NestedTransition::Child(DoorTransition::Push),
+        );
+        assert_eq!(state.parent, 1);
+        assert_eq!(state.child, None);
+    }
+
+    #[test]
+    fn nested_transitions_dispatch_to_parent_or_active_child() {
+        let mut runner = TestRunner::deterministic();
+
+        let parent_state = NestedState::<SessionMachine> {
+            parent: 0,
+            child: None,
+        };
+        let transition =
+            <Nested<SessionMachine> as ReferenceStateMachine>::transitions(
+                &parent_state,
+            )
+            .new_tree(&mut runner)
+            .unwrap()
+            .current();
+        assert!(matches!(
+            transition,
+            NestedTransition::Parent(SessionTransition::StartDoor)
+        ));
+
+        let child_state = NestedState::<SessionMachine> {
+            parent: 0,
+            child: Some(0),
+        };
+        let transition =
+            <Nested<SessionMachine> as ReferenceStateMachine>::transitions(
+                &child_state,
+            )
+            .new_tree(&mut runner)
+            .unwrap()
+            .current();
+        assert!(matches!(
+            transition,
+            NestedTransition::Child(DoorTransition::Push)
+        ));
+    }
+
+    #[test]
+    fn nested_preconditions_reject_transition_from_the_wrong_phase() {
+        let parent_state = NestedState::<SessionMachine> {
+            parent: 0,
+            child: None,
+        };
+        assert!(
+            !<Nested<SessionMachine> as ReferenceStateMachine>::preconditions(
+                &parent_state,
+                &NestedTransition::Child(DoorTransition::Push),
+            )
+        );
+        assert!(<Nested<SessionMachine> as ReferenceStateMachine>::preconditions(
+            &parent_state,
+            &NestedTransition::Parent(SessionTransition::StartDoor),
+        ));
+    }
+
+    /// A trivial parent/child pair used only to exercise [`Nested`]'s
+    /// dispatch logic: the parent repeatedly starts a child sub-run that
+    /// completes after exactly two of its own transitions.
+    mod session_state_machine {
+        use proptest::prelude::*;
+
+        use crate::ReferenceStateMachine;
+
+        use super::{Complete, NestedStateMachine};
+
+        /// The child: opens a door, one push at a time.
+        pub struct DoorMachine;
+
+        #[derive(Clone, Debug)]
+        pub enum DoorTransition {
+            Push,
+        }
+
+        impl ReferenceStateMachine for DoorMachine {
+            /// The number of pushes applied so far.
+            type State = u32;
+            type Transition = DoorTransition;
+
+            fn init_state() -> BoxedStrategy<Self::State> {
+                Just(0).boxed()
+            }
+
+            fn transitions(
+                _state: &Self::State,
+            ) -> BoxedStrategy<Self::Transition> {
+                Just(DoorTransition::Push).boxed()
+            }
+
+            fn apply(
+                state: Self::State,
+                _transition: &Self::Transition,
+            ) -> Self::State {
+                state + 1
+            }
+        }
+
+        /// The parent: starts a new door sub-run, then waits for it to
+        /// finish before starting the next one.
+        pub struct SessionMachine;
+
+        #[derive(Clone, Debug)]
+        pub enum SessionTransition {
+            StartDoor,
+        }
+
+        impl NestedStateMachine for SessionMachine {
+            /// The number of doors fully opened so far.
+            type State = u32;
+            type Transition = SessionTransition;
+            type Child = DoorMachine;
+
+            fn init_state() -> BoxedStrategy<Self::State> {
+                Just(0).boxed()
+            }
+
+            fn transitions(
+                _state: &Self::State,
+            ) -> BoxedStrategy<Self::Transition> {
+                Just(SessionTransition::StartDoor).boxed()
+            }
+
+            fn apply(
+                state: Self::State,
+                _transition: &Self::Transition,
+            ) -> Self::State {
+                state
+            }
+
+            fn enter_child(
+                _state: &Self::State,
+                _transition: &Self::Transition,
+            ) -> Option<<Self::Child as ReferenceStateMachine>::State> {
+                Some(0)
+            }
+
+            fn child_complete(
+                child_state: &<Self::Child as ReferenceStateMachine>::State,
+            ) -> Complete {
+                if *child_state >= 2 {
+                    Complete::Done
+                } else {
+                    Complete::Running
+                }
+            }
+
+            fn exit_child(
+                state: Self::State,
+                _child_state: <Self::Child as ReferenceStateMachine>::State,
+            ) -> Self::State {
+                state + 1
+            }
+        }
+    }
+}
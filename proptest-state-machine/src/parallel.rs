@@ -0,0 +1,955 @@
+//-
+// Copyright 2023 The proptest developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Strategies for testing concurrent systems under test against a reference
+//! state machine, checking the observed results for linearizability.
+
+use proptest::bits::{BitSetLike, VarBitSet};
+use proptest::collection::SizeRange;
+use proptest::num::sample_uniform_incl;
+use proptest::std_facade::fmt::{self, Debug, Formatter};
+use proptest::std_facade::Vec;
+use proptest::strategy::{NewTree, Strategy, ValueTree};
+use proptest::test_runner::{Reason, TestRunner};
+
+use crate::strategy::{
+    resample_with_fallback, Sequential, SequentialValueTree, TransitionState,
+    DEFAULT_MAX_LOCAL_RESAMPLES,
+};
+use TransitionState::*;
+
+/// A parallel strategy generates a sequential prefix of transitions, exactly
+/// like [`Sequential`] does, that is run to reach some reference state `S`,
+/// followed by `threads` concurrent "lanes" of transitions, each generated
+/// independently starting from `S`. Each lane is intended to be executed on
+/// its own thread against the system under test, concurrently with the
+/// other lanes.
+///
+/// The generated value is `(State, Vec<Transition>, Vec<Vec<Transition>>)`:
+/// the initial state, the prefix's transitions, and one transition sequence
+/// per thread. A test harness is expected to fold the prefix over the
+/// initial state to reach `S`, run every lane concurrently against the
+/// system under test starting from `S`, record each transition's observed
+/// return value, and then check the recording with
+/// [`check_linearizable`].
+///
+/// The shrinking strategy tries, in order: deleting transitions from the
+/// back of each lane; moving a lane's leading transition into the
+/// sequential prefix (reducing the amount of concurrency in the
+/// counterexample); shrinking the individual transitions in a lane; and
+/// finally shrinking the sequential prefix exactly as [`Sequential`] does.
+pub struct Parallel<State, Transition, StateStrategy, TransitionStrategy> {
+    pub(crate) prefix_size: SizeRange,
+    pub(crate) threads: usize,
+    pub(crate) thread_size: SizeRange,
+    pub(crate) init_state: fn() -> StateStrategy,
+    pub(crate) preconditions: fn(state: &State, transition: &Transition) -> bool,
+    pub(crate) transitions: fn(state: &State) -> TransitionStrategy,
+    pub(crate) next: fn(state: State, transition: &Transition) -> State,
+    pub(crate) fallback: fn(state: &State) -> Option<TransitionStrategy>,
+}
+
+impl<State, Transition, StateStrategy, TransitionStrategy> Debug
+    for Parallel<State, Transition, StateStrategy, TransitionStrategy>
+{
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Parallel")
+            .field("prefix_size", &self.prefix_size)
+            .field("threads", &self.threads)
+            .field("thread_size", &self.thread_size)
+            .finish()
+    }
+}
+
+impl<
+        State: Clone + Debug,
+        Transition: Clone + Debug,
+        StateStrategy: Strategy<Value = State>,
+        TransitionStrategy: Strategy<Value = Transition>,
+    > Strategy for Parallel<State, Transition, StateStrategy, TransitionStrategy>
+{
+    type Tree = ParallelValueTree<
+        State,
+        Transition,
+        StateStrategy::Tree,
+        TransitionStrategy::Tree,
+    >;
+    type Value = (State, Vec<Transition>, Vec<Vec<Transition>>);
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        let prefix = Sequential::new(
+            self.prefix_size.clone(),
+            self.init_state,
+            self.preconditions,
+            self.transitions,
+            self.next,
+            self.fallback,
+        )
+        .new_tree(runner)?;
+
+        // Fold the prefix's transitions over its initial state to find the
+        // reference state `S` every lane branches off from.
+        let (prefix_initial_state, prefix_transitions) = prefix.current();
+        let branch_state = prefix_transitions
+            .iter()
+            .fold(prefix_initial_state, |state, transition| {
+                (self.next)(state, transition)
+            });
+
+        let mut lanes = Vec::with_capacity(self.threads);
+        for _ in 0..self.threads {
+            lanes.push(Lane::new(
+                branch_state.clone(),
+                self.preconditions,
+                self.transitions,
+                self.next,
+                self.fallback,
+                &self.thread_size,
+                runner,
+            )?);
+        }
+
+        let mut tree = ParallelValueTree {
+            prefix,
+            next: self.next,
+            preconditions: self.preconditions,
+            serialized: Vec::new(),
+            lanes,
+            shrink: None,
+            last_shrink: None,
+        };
+        let first = if tree.lanes.is_empty() {
+            None
+        } else {
+            ParallelValueTree::<
+                State,
+                Transition,
+                StateStrategy::Tree,
+                TransitionStrategy::Tree,
+            >::thread_start(tree.lanes.len() as isize - 1)
+        };
+        tree.shrink = tree.resume(first).or(Some(Prefix));
+
+        Ok(tree)
+    }
+}
+
+/// A single, independently shrinkable sequence of transitions starting from
+/// a fixed reference state. Generation follows the same "resample, then
+/// fall back" contract as [`Sequential`] (via
+/// [`crate::strategy::resample_with_fallback`]), and shrinking mirrors
+/// [`SequentialValueTree`]'s deletion/per-transition shrinks, but a lane has
+/// no recorder or coverage-guided weighting of its own - those are
+/// [`Sequential`]-only - and its starting state is given to it rather than
+/// generated from [`super::ReferenceStateMachine::init_state`], since every
+/// lane of a parallel run branches off from the same state reached by the
+/// sequential prefix.
+struct Lane<State, Transition, TransitionValueTree> {
+    start_state: State,
+    preconditions: fn(&State, &Transition) -> bool,
+    next: fn(State, &Transition) -> State,
+    transitions: Vec<TransitionValueTree>,
+    acceptable_transitions: Vec<(TransitionState, Transition)>,
+    included_transitions: VarBitSet,
+    shrinkable_transitions: VarBitSet,
+    max_ix: usize,
+}
+
+impl<
+        State: Clone,
+        Transition: Clone,
+        TransitionValueTree: ValueTree<Value = Transition>,
+    > Lane<State, Transition, TransitionValueTree>
+{
+    fn new<TransitionStrategy: Strategy<Value = Transition, Tree = TransitionValueTree>>(
+        start_state: State,
+        preconditions: fn(&State, &Transition) -> bool,
+        transitions_strategy: fn(&State) -> TransitionStrategy,
+        next: fn(State, &Transition) -> State,
+        fallback: fn(&State) -> Option<TransitionStrategy>,
+        size: &SizeRange,
+        runner: &mut TestRunner,
+    ) -> Result<Self, Reason> {
+        let (min_size, end) = size.start_end_incl();
+        let max_size = sample_uniform_incl(runner, min_size, end);
+
+        let mut transitions = Vec::with_capacity(max_size);
+        let mut acceptable_transitions = Vec::with_capacity(max_size);
+
+        // Sample transitions until we reach `max_size`, or until local
+        // resampling and `fallback` both run out of ways to make progress,
+        // exactly as `Sequential`'s own generation loop does.
+        let mut state = start_state.clone();
+        while transitions.len() < max_size {
+            match resample_with_fallback(
+                runner,
+                &state,
+                preconditions,
+                transitions_strategy,
+                fallback,
+                DEFAULT_MAX_LOCAL_RESAMPLES,
+            )? {
+                Some((transition_tree, transition)) => {
+                    state = next(state, &transition);
+                    transitions.push(transition_tree);
+                    acceptable_transitions
+                        .push((TransitionState::Accepted, transition));
+                }
+                None if transitions.len() >= min_size => break,
+                None => {
+                    runner.reject_local(
+                        "Pre-conditions were not satisfied after exhausting local resamples",
+                    )?;
+                }
+            }
+        }
+
+        // `min_size` may be 0, and the early-stop above can leave the lane
+        // empty even then, so there may be no valid index at all.
+        let max_ix = transitions.len().saturating_sub(1);
+        let included_transitions = VarBitSet::saturated(transitions.len());
+        let shrinkable_transitions = VarBitSet::saturated(transitions.len());
+
+        Ok(Lane {
+            start_state,
+            preconditions,
+            next,
+            transitions,
+            acceptable_transitions,
+            included_transitions,
+            shrinkable_transitions,
+            max_ix,
+        })
+    }
+
+    /// The lane's currently included transitions.
+    fn current_transitions(&self) -> Vec<Transition> {
+        self.acceptable_transitions
+            .iter()
+            .enumerate()
+            .filter(|&(ix, _)| self.included_transitions.test(ix))
+            .map(|(_, (_, transition))| transition.clone())
+            .collect()
+    }
+
+    /// Whether the lane has no included transitions left.
+    fn is_empty(&self) -> bool {
+        self.included_transitions.count() == 0
+    }
+
+    /// Check that the lane's included transitions are still acceptable when
+    /// starting from `self.start_state`.
+    fn check_acceptable(&self) -> bool {
+        let mut state = self.start_state.clone();
+        for transition in self.current_transitions() {
+            if (self.preconditions)(&state, &transition) {
+                state = (self.next)(state, &transition);
+            } else {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Delete the transition at index `ix` from the back of the lane,
+    /// undoing the delete if it leaves the lane unacceptable.
+    fn try_delete(&mut self, ix: usize) -> bool {
+        self.included_transitions.clear(ix);
+        if self.check_acceptable() {
+            self.shrinkable_transitions.clear(ix);
+            true
+        } else {
+            self.included_transitions.set(ix);
+            false
+        }
+    }
+
+    fn undo_delete(&mut self, ix: usize) {
+        self.included_transitions.set(ix);
+        self.shrinkable_transitions.set(ix);
+    }
+
+    /// The first included transition's index, if the lane is non-empty.
+    fn first_included_ix(&self) -> Option<usize> {
+        (0..=self.max_ix).find(|&ix| self.included_transitions.test(ix))
+    }
+}
+
+/// A shrinking operation for a [`ParallelValueTree`].
+#[derive(Clone, Copy, Debug)]
+enum ParallelShrink {
+    /// Delete a transition from the back of a lane (by thread index).
+    DeleteLaneTransition(usize, usize),
+    /// Move a lane's leading transition (by thread and transition index)
+    /// into the sequential prefix, reducing the amount of concurrency in
+    /// the counterexample.
+    Serialize(usize, usize),
+    /// Shrink an individual transition within a lane.
+    LaneTransition(usize, usize),
+    /// Shrink the sequential prefix (and, eventually, the initial state).
+    Prefix,
+}
+use ParallelShrink::*;
+
+/// The generated value tree for a parallel (linearizability-checking) state
+/// machine strategy. See [`Parallel`].
+pub struct ParallelValueTree<State, Transition, StateValueTree, TransitionValueTree> {
+    /// The sequential prefix, reusing `Sequential`'s own generation and
+    /// shrinking machinery verbatim.
+    prefix: SequentialValueTree<State, Transition, StateValueTree, TransitionValueTree>,
+    next: fn(State, &Transition) -> State,
+    preconditions: fn(&State, &Transition) -> bool,
+    /// Transitions moved out of a lane and into the effective prefix by the
+    /// `Serialize` shrink. They run, in order, right after `prefix`'s own
+    /// transitions and before any lane starts.
+    serialized: Vec<Transition>,
+    /// One independently generated and shrunk lane of transitions per
+    /// thread.
+    lanes: Vec<Lane<State, Transition, TransitionValueTree>>,
+    shrink: Option<ParallelShrink>,
+    last_shrink: Option<ParallelShrink>,
+}
+
+impl<
+        State: Clone + Debug,
+        Transition: Clone + Debug,
+        StateValueTree: ValueTree<Value = State>,
+        TransitionValueTree: ValueTree<Value = Transition>,
+    > ParallelValueTree<State, Transition, StateValueTree, TransitionValueTree>
+{
+    /// Index of the transition that would be moved by
+    /// `Serialize(thread, ..)`, if the lane has any included transitions
+    /// left.
+    fn serialize_ix(&self, thread: usize) -> Option<usize> {
+        self.lanes[thread].first_included_ix()
+    }
+
+    /// The reference state reached after `prefix` and `serialized` have run,
+    /// which is the state every lane branches off from.
+    fn branch_state(&self) -> State {
+        let (initial_state, prefix_transitions) = self.prefix.current();
+        prefix_transitions
+            .iter()
+            .chain(self.serialized.iter())
+            .fold(initial_state, |state, transition| {
+                (self.next)(state, transition)
+            })
+    }
+
+    /// The shrink step that starts thread `thread`'s pipeline (delete, then
+    /// serialize, then per-transition shrinking), or `None` once every
+    /// thread has been covered.
+    fn thread_start(thread: isize) -> Option<ParallelShrink> {
+        if thread < 0 {
+            None
+        } else {
+            Some(DeleteLaneTransition(thread as usize, usize::MAX))
+        }
+    }
+
+    /// Where to go once thread `thread`'s whole pipeline is exhausted: the
+    /// previous thread's pipeline, or the sequential prefix once every
+    /// thread is done.
+    fn thread_done(&self, thread: usize) -> Option<ParallelShrink> {
+        if thread == 0 {
+            Some(Prefix)
+        } else {
+            self.resume(Self::thread_start(thread as isize - 1))
+        }
+    }
+
+    /// Skip over shrink steps that are no-ops for the lane/index they name
+    /// (an already excluded thread, or nothing left to serialize), moving
+    /// on to the next phase instead.
+    fn resume(&self, shrink: Option<ParallelShrink>) -> Option<ParallelShrink> {
+        match shrink {
+            Some(DeleteLaneTransition(thread, _)) if self.lanes[thread].is_empty() => {
+                self.resume(Some(Serialize(thread, 0)))
+            }
+            Some(DeleteLaneTransition(thread, ix)) if ix == usize::MAX => {
+                Some(DeleteLaneTransition(thread, self.lanes[thread].max_ix))
+            }
+            Some(Serialize(thread, _)) => match self.serialize_ix(thread) {
+                Some(ix) => Some(Serialize(thread, ix)),
+                None => self.resume(Some(LaneTransition(thread, usize::MAX))),
+            },
+            Some(LaneTransition(thread, ix)) if ix == usize::MAX => {
+                Some(LaneTransition(thread, self.lanes[thread].max_ix))
+            }
+            shrink => shrink,
+        }
+    }
+
+    fn next_lane_shrink(&self, thread: usize, ix: usize) -> Option<ParallelShrink> {
+        if ix == 0 {
+            self.thread_done(thread)
+        } else {
+            Some(LaneTransition(thread, ix - 1))
+        }
+    }
+
+    fn try_simplify(&mut self) -> bool {
+        match self.shrink {
+            Some(DeleteLaneTransition(thread, ix)) => {
+                self.last_shrink = self.shrink;
+                self.shrink = if ix == 0 {
+                    self.resume(Some(Serialize(thread, 0)))
+                } else {
+                    Some(DeleteLaneTransition(thread, ix - 1))
+                };
+                if self.lanes[thread].try_delete(ix) {
+                    true
+                } else {
+                    self.last_shrink = None;
+                    self.try_simplify()
+                }
+            }
+            Some(Serialize(thread, ix)) => {
+                if self.serialize(thread, ix) {
+                    self.last_shrink = Some(Serialize(thread, ix));
+                    // Keep serializing the same lane: it may have another
+                    // included transition at its new head.
+                    self.shrink = self.resume(Some(Serialize(thread, ix)));
+                    true
+                } else {
+                    self.shrink =
+                        self.resume(Some(LaneTransition(thread, usize::MAX)));
+                    self.try_simplify()
+                }
+            }
+            Some(LaneTransition(thread, ix)) => {
+                if !self.lanes[thread].included_transitions.test(ix) {
+                    self.shrink = self.next_lane_shrink(thread, ix);
+                    return self.try_simplify();
+                }
+                if self.lanes[thread].transitions[ix].simplify() {
+                    self.last_shrink = self.shrink;
+                    if self.lanes[thread].check_acceptable() {
+                        let current = self.lanes[thread].transitions[ix].current();
+                        self.lanes[thread].acceptable_transitions[ix] = (Accepted, current);
+                        true
+                    } else {
+                        self.lanes[thread].shrinkable_transitions.clear(ix);
+                        self.shrink = self.next_lane_shrink(thread, ix);
+                        self.try_simplify()
+                    }
+                } else {
+                    self.lanes[thread].shrinkable_transitions.clear(ix);
+                    self.shrink = self.next_lane_shrink(thread, ix);
+                    self.try_simplify()
+                }
+            }
+            Some(Prefix) => {
+                self.last_shrink = self.shrink;
+                if self.prefix.simplify() {
+                    true
+                } else {
+                    self.last_shrink = None;
+                    self.shrink = None;
+                    false
+                }
+            }
+            None => false,
+        }
+    }
+
+    /// Move `thread`'s leading included transition (at `ix`) into the
+    /// serialized prefix, if doing so is still acceptable for both the
+    /// prefix and the remaining lane.
+    fn serialize(&mut self, thread: usize, ix: usize) -> bool {
+        let transition = self.lanes[thread].acceptable_transitions[ix].1.clone();
+        if !(self.preconditions)(&self.branch_state(), &transition) {
+            return false;
+        }
+        self.lanes[thread].included_transitions.clear(ix);
+        if !self.lanes[thread].check_acceptable() {
+            self.lanes[thread].included_transitions.set(ix);
+            return false;
+        }
+        self.serialized.push(transition);
+        true
+    }
+}
+
+impl<
+        State: Clone + Debug,
+        Transition: Clone + Debug,
+        StateValueTree: ValueTree<Value = State>,
+        TransitionValueTree: ValueTree<Value = Transition>,
+    > ValueTree for ParallelValueTree<State, Transition, StateValueTree, TransitionValueTree>
+{
+    type Value = (State, Vec<Transition>, Vec<Vec<Transition>>);
+
+    fn current(&self) -> Self::Value {
+        let (initial_state, mut prefix_transitions) = self.prefix.current();
+        prefix_transitions.extend(self.serialized.iter().cloned());
+        let lanes = self
+            .lanes
+            .iter()
+            .map(Lane::current_transitions)
+            .collect();
+        (initial_state, prefix_transitions, lanes)
+    }
+
+    fn simplify(&mut self) -> bool {
+        self.try_simplify()
+    }
+
+    fn complicate(&mut self) -> bool {
+        match self.last_shrink {
+            None => false,
+            Some(DeleteLaneTransition(thread, ix)) => {
+                self.lanes[thread].undo_delete(ix);
+                self.last_shrink = None;
+                true
+            }
+            Some(Serialize(thread, ix)) => {
+                // Undo the move: drop it from the serialized prefix and
+                // re-include it at its original position in the lane.
+                self.serialized.pop();
+                self.lanes[thread].included_transitions.set(ix);
+                // `ix` is the lane's head again, so the pending
+                // `Serialize` step no longer names the leading transition;
+                // move on to this lane's per-transition shrinking instead.
+                self.shrink =
+                    self.resume(Some(LaneTransition(thread, usize::MAX)));
+                self.last_shrink = None;
+                true
+            }
+            Some(LaneTransition(thread, ix)) => {
+                if self.lanes[thread].transitions[ix].complicate()
+                    && self.lanes[thread].check_acceptable()
+                {
+                    let current = self.lanes[thread].transitions[ix].current();
+                    self.lanes[thread].acceptable_transitions[ix] = (Accepted, current);
+                    return true;
+                }
+                self.last_shrink = None;
+                false
+            }
+            Some(Prefix) => {
+                self.last_shrink = None;
+                self.prefix.complicate()
+            }
+        }
+    }
+}
+
+/// A single concurrently-executed operation recorded while running a
+/// [`Parallel`] lane against the system under test.
+///
+/// `invocation` and `response` are indices into a single, global, monotonic
+/// clock shared by every thread (e.g. an `AtomicUsize` the harness
+/// increments once when a call starts and once when it returns) rather than
+/// per-thread counters, so that operations from different threads can be
+/// compared for real-time order: if `a.response < b.invocation`, `a`
+/// necessarily finished before `b` was invoked, and a linearization must
+/// order `a` before `b`.
+#[derive(Clone, Debug)]
+pub struct Operation<Transition, Return> {
+    /// The transition that was applied to the system under test.
+    pub transition: Transition,
+    /// The return value observed from the system under test.
+    pub observed: Return,
+    /// The global clock index at which the call was invoked.
+    pub invocation: usize,
+    /// The global clock index at which the call returned.
+    pub response: usize,
+}
+
+/// Checks whether the `operations` recorded while running each thread's
+/// transitions concurrently against the system under test are linearizable
+/// with respect to the reference model reached by `preconditions` and
+/// `apply` from `initial_state`.
+///
+/// A recording is linearizable if there exists *some* interleaving of all
+/// the threads' operations that (a) respects each thread's own program
+/// order, (b) respects the real-time order given by every operation's
+/// `invocation`/`response` indices (if one operation's response precedes
+/// another's invocation, the first must be linearized before the second),
+/// and (c), when replayed against the reference model starting from
+/// `initial_state`, satisfies every precondition and reproduces exactly the
+/// observed return value for every operation.
+///
+/// The search is a depth-first backtracking search over the state
+/// `(reference_state, cursor_per_thread)`. At each step, the candidates are
+/// only the *minimal* operations: among each thread's next not-yet-applied
+/// operation, those whose `invocation` precedes the earliest `response`
+/// still outstanding among all threads' next operations. Real-time order
+/// guarantees at least one such operation could legally be linearized next;
+/// restricting the search to them (instead of every thread's next
+/// operation) prunes interleavings real time already rules out. The first
+/// candidate whose `preconditions` holds and whose `apply` result matches
+/// what was `observed` is taken, and the search recurses with that thread's
+/// cursor advanced, backtracking on a mismatch. The search is capped at
+/// `max_search_nodes` explored nodes to avoid factorial blowup on long
+/// traces; exceeding the cap is treated as "not linearizable" (the witness
+/// should be shrunk to fewer, shorter lanes before investigating further).
+pub fn check_linearizable<State, Transition, Return>(
+    initial_state: State,
+    operations: &[Vec<Operation<Transition, Return>>],
+    max_search_nodes: usize,
+    preconditions: impl Fn(&State, &Transition) -> bool,
+    apply: impl Fn(&State, &Transition) -> (State, Return),
+) -> bool
+where
+    State: Clone,
+    Return: PartialEq,
+{
+    let mut cursors = vec![0usize; operations.len()];
+    let mut budget = max_search_nodes;
+    search(
+        &initial_state,
+        operations,
+        &preconditions,
+        &apply,
+        &mut cursors,
+        &mut budget,
+    )
+}
+
+/// The threads whose next operation is *minimal*: its `invocation` precedes
+/// the earliest `response` among every thread's next not-yet-applied
+/// operation. Real time permits a minimal operation to be linearized next;
+/// a non-minimal one necessarily has some other pending operation that
+/// finished strictly before it was invoked, so real time forces that other
+/// operation first.
+fn minimal_threads<Transition, Return>(
+    operations: &[Vec<Operation<Transition, Return>>],
+    cursors: &[usize],
+) -> Vec<usize> {
+    let pending = |thread: usize| operations[thread].get(cursors[thread]);
+
+    let earliest_response = (0..operations.len())
+        .filter_map(pending)
+        .map(|op| op.response)
+        .min();
+
+    let Some(earliest_response) = earliest_response else {
+        return Vec::new();
+    };
+
+    (0..operations.len())
+        .filter(|&thread| {
+            pending(thread)
+                .is_some_and(|op| op.invocation <= earliest_response)
+        })
+        .collect()
+}
+
+fn search<State, Transition, Return>(
+    state: &State,
+    operations: &[Vec<Operation<Transition, Return>>],
+    preconditions: &impl Fn(&State, &Transition) -> bool,
+    apply: &impl Fn(&State, &Transition) -> (State, Return),
+    cursors: &mut [usize],
+    budget: &mut usize,
+) -> bool
+where
+    State: Clone,
+    Return: PartialEq,
+{
+    if *budget == 0 {
+        return false;
+    }
+    *budget -= 1;
+
+    if cursors
+        .iter()
+        .zip(operations.iter())
+        .all(|(&cursor, thread)| cursor == thread.len())
+    {
+        return true;
+    }
+
+    for thread in minimal_threads(operations, cursors) {
+        let cursor = cursors[thread];
+        let op = &operations[thread][cursor];
+        if !preconditions(state, &op.transition) {
+            continue;
+        }
+        let (next_state, result) = apply(state, &op.transition);
+        if result != op.observed {
+            continue;
+        }
+
+        cursors[thread] += 1;
+        if search(
+            &next_state,
+            operations,
+            preconditions,
+            apply,
+            cursors,
+            budget,
+        ) {
+            return true;
+        }
+        cursors[thread] -= 1;
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use proptest::test_runner::TestRunner;
+
+    use crate::ReferenceStateMachine;
+    use counter_state_machine::*;
+
+    const PREFIX_SIZE: usize = 8;
+    const THREADS: usize = 3;
+    const THREAD_SIZE: usize = 4;
+
+    type TestParallelValueTree = ParallelValueTree<
+        TestState,
+        TestTransition,
+        <proptest::strategy::BoxedStrategy<TestState> as Strategy>::Tree,
+        <proptest::strategy::BoxedStrategy<TestTransition> as Strategy>::Tree,
+    >;
+
+    fn deterministic_parallel_value_tree() -> TestParallelValueTree {
+        let parallel =
+            <CounterStateMachine as ReferenceStateMachine>::parallel_strategy(
+                PREFIX_SIZE,
+                THREADS,
+                THREAD_SIZE,
+            );
+        let mut runner = TestRunner::deterministic();
+        parallel.new_tree(&mut runner).unwrap()
+    }
+
+    /// Replay `value_tree`'s current prefix, then every lane independently
+    /// from the state the prefix reaches, asserting every transition still
+    /// satisfies `preconditions` - the same invariant
+    /// `strategy::test::test_state_machine_sequential_value_tree_aux`
+    /// checks for `SequentialValueTree`.
+    fn check_preconditions(value_tree: &TestParallelValueTree) {
+        let (initial_state, prefix, lanes) = value_tree.current();
+
+        let mut state = initial_state;
+        for transition in &prefix {
+            assert!(CounterStateMachine::preconditions(&state, transition));
+            state = CounterStateMachine::apply(state, transition);
+        }
+        let branch_state = state;
+
+        for lane in &lanes {
+            let mut state = branch_state;
+            for transition in lane {
+                assert!(CounterStateMachine::preconditions(&state, transition));
+                state = CounterStateMachine::apply(state, transition);
+            }
+        }
+    }
+
+    #[test]
+    fn parallel_value_tree_shrinks_preserve_preconditions() {
+        let mut value_tree = deterministic_parallel_value_tree();
+        check_preconditions(&value_tree);
+
+        let mut simplifications = 0;
+        while value_tree.simplify() {
+            check_preconditions(&value_tree);
+            simplifications += 1;
+        }
+        assert!(simplifications > 0);
+
+        // Complicating all the way back out should stay acceptable at every
+        // step too.
+        while value_tree.complicate() {
+            check_preconditions(&value_tree);
+        }
+    }
+
+    #[test]
+    fn serialize_retries_the_same_lane_until_exhausted() {
+        let mut value_tree = deterministic_parallel_value_tree();
+        let thread = 0;
+
+        // Jump straight to the `Serialize` phase on a freshly generated
+        // lane, before `DeleteLaneTransition` gets a chance to prune it, so
+        // every transition the lane started with is still there to
+        // serialize.
+        value_tree.shrink = value_tree.resume(Some(Serialize(thread, 0)));
+
+        let mut serialized = 0;
+        while matches!(value_tree.shrink, Some(Serialize(t, _)) if t == thread) {
+            if !value_tree.try_simplify() {
+                break;
+            }
+            serialized += 1;
+        }
+
+        assert!(
+            serialized > 1,
+            "expected more than one transition to be serialized from the \
+             same lane in a row, got {serialized}"
+        );
+    }
+
+    /// Build a trivial `(state, transition) -> (state, observed)` pair for
+    /// `check_linearizable`: the transition is the delta applied to an `i32`
+    /// counter, and the observed return is the counter's value afterward.
+    fn apply(state: &i32, transition: &i32) -> (i32, i32) {
+        let next = state + transition;
+        (next, next)
+    }
+
+    #[test]
+    fn check_linearizable_accepts_an_interleaving_consistent_with_real_time() {
+        // Thread 0 runs strictly before thread 1 in real time (its response
+        // precedes thread 1's invocation), so the only valid linearization
+        // applies thread 0 first.
+        let operations = vec![
+            vec![Operation {
+                transition: 1,
+                observed: 1,
+                invocation: 0,
+                response: 1,
+            }],
+            vec![Operation {
+                transition: 1,
+                observed: 2,
+                invocation: 2,
+                response: 3,
+            }],
+        ];
+
+        assert!(check_linearizable(
+            0,
+            &operations,
+            1_000,
+            |_state, _transition| true,
+            apply,
+        ));
+    }
+
+    #[test]
+    fn check_linearizable_rejects_an_interleaving_inconsistent_with_real_time()
+    {
+        // Same real-time constraint as above (thread 0 strictly first), but
+        // the observed values only match the other order.
+        let operations = vec![
+            vec![Operation {
+                transition: 1,
+                observed: 2,
+                invocation: 0,
+                response: 1,
+            }],
+            vec![Operation {
+                transition: 1,
+                observed: 1,
+                invocation: 2,
+                response: 3,
+            }],
+        ];
+
+        assert!(!check_linearizable(
+            0,
+            &operations,
+            1_000,
+            |_state, _transition| true,
+            apply,
+        ));
+    }
+
+    #[test]
+    fn check_linearizable_accepts_either_order_for_overlapping_operations() {
+        // Both operations overlap in real time (neither response precedes
+        // the other's invocation), so either order is a valid
+        // linearization; this one is only consistent with thread 1 going
+        // first.
+        let operations = vec![
+            vec![Operation {
+                transition: 1,
+                observed: 2,
+                invocation: 0,
+                response: 10,
+            }],
+            vec![Operation {
+                transition: 1,
+                observed: 1,
+                invocation: 1,
+                response: 5,
+            }],
+        ];
+
+        assert!(check_linearizable(
+            0,
+            &operations,
+            1_000,
+            |_state, _transition| true,
+            apply,
+        ));
+    }
+
+    /// A minimal reference state machine used only to exercise
+    /// [`Parallel`]/[`ParallelValueTree`] generation and shrinking.
+    mod counter_state_machine {
+        use proptest::prelude::*;
+
+        use crate::ReferenceStateMachine;
+
+        pub struct CounterStateMachine;
+
+        pub type TestState = i32;
+
+        #[derive(Clone, Debug)]
+        pub enum TestTransition {
+            Incr,
+            DecrNonZero,
+        }
+
+        impl ReferenceStateMachine for CounterStateMachine {
+            type State = TestState;
+            type Transition = TestTransition;
+
+            fn init_state() -> BoxedStrategy<Self::State> {
+                Just(0).boxed()
+            }
+
+            fn transitions(
+                state: &Self::State,
+            ) -> BoxedStrategy<Self::Transition> {
+                if *state == 0 {
+                    Just(TestTransition::Incr).boxed()
+                } else {
+                    prop_oneof![
+                        1 => Just(TestTransition::DecrNonZero),
+                        2 => Just(TestTransition::Incr),
+                    ]
+                    .boxed()
+                }
+            }
+
+            fn apply(
+                state: Self::State,
+                transition: &Self::Transition,
+            ) -> Self::State {
+                match transition {
+                    TestTransition::Incr => state + 1,
+                    TestTransition::DecrNonZero => state - 1,
+                }
+            }
+
+            fn preconditions(
+                state: &Self::State,
+                transition: &Self::Transition,
+            ) -> bool {
+                match transition {
+                    TestTransition::Incr => true,
+                    TestTransition::DecrNonZero => *state > 0,
+                }
+            }
+        }
+    }
+}
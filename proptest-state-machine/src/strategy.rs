@@ -9,6 +9,9 @@
 
 //! Strategies used for abstract state machine testing.
 
+use std::cell::RefCell;
+use std::rc::Rc;
+
 use proptest::bits::{BitSetLike, VarBitSet};
 use proptest::collection::SizeRange;
 use proptest::num::sample_uniform_incl;
@@ -16,7 +19,76 @@ use proptest::std_facade::fmt::{Debug, Formatter, Result};
 use proptest::std_facade::Vec;
 use proptest::strategy::BoxedStrategy;
 use proptest::strategy::{NewTree, Strategy, ValueTree};
-use proptest::test_runner::TestRunner;
+use proptest::test_runner::{Reason, TestRunner};
+
+/// The [`Sequential`] strategy returned by
+/// [`ReferenceStateMachine::sequential_strategy`]'s default implementation,
+/// which boxes both the state and transition strategies.
+pub type BoxedSequential<State, Transition> =
+    Sequential<State, Transition, BoxedStrategy<State>, BoxedStrategy<Transition>>;
+
+/// The [`crate::parallel::Parallel`] strategy returned by
+/// [`ReferenceStateMachine::parallel_strategy`]'s default implementation,
+/// which boxes both the state and transition strategies.
+pub type BoxedParallel<State, Transition> = crate::parallel::Parallel<
+    State,
+    Transition,
+    BoxedStrategy<State>,
+    BoxedStrategy<Transition>,
+>;
+
+use crate::coverage::CoverageFeedback;
+use crate::graph::TransitionRecorder;
+
+/// The default number of times generation will locally resample the
+/// transition strategy from a given state and have it rejected by the
+/// pre-conditions, before giving up on that state (see
+/// [`Sequential::with_max_local_resamples`]).
+pub(crate) const DEFAULT_MAX_LOCAL_RESAMPLES: usize = 10;
+
+/// Generate the next transition from `state`: resample `transitions` from
+/// the same state, locally, up to `max_local_resamples` times, accepting
+/// the first that satisfies `preconditions`, then fall back to `fallback`
+/// if every resample was rejected. Returns `Ok(None)` if there's no
+/// fallback and every resample was rejected, leaving it to the caller to
+/// decide whether to stop generation early or consume proptest's global
+/// rejection budget instead.
+///
+/// This is the same "resample, then fall back" contract [`Sequential`]'s
+/// own generation loop follows; [`crate::parallel::Lane`] uses it as-is.
+pub(crate) fn resample_with_fallback<State, Transition, TransitionStrategy>(
+    runner: &mut TestRunner,
+    state: &State,
+    preconditions: fn(&State, &Transition) -> bool,
+    transitions: fn(&State) -> TransitionStrategy,
+    fallback: fn(&State) -> Option<TransitionStrategy>,
+    max_local_resamples: usize,
+) -> std::result::Result<Option<(TransitionStrategy::Tree, Transition)>, Reason>
+where
+    TransitionStrategy: Strategy<Value = Transition>,
+{
+    for _ in 0..max_local_resamples {
+        let transition_tree = transitions(state).new_tree(runner)?;
+        let transition = transition_tree.current();
+        if preconditions(state, &transition) {
+            return Ok(Some((transition_tree, transition)));
+        }
+    }
+
+    match fallback(state) {
+        Some(fallback) => {
+            let fallback_tree = fallback.new_tree(runner)?;
+            let transition = fallback_tree.current();
+            Ok(Some((fallback_tree, transition)))
+        }
+        None => Ok(None),
+    }
+}
+
+/// The default number of candidate transitions drawn per position when
+/// coverage-guided weighting is enabled (see
+/// [`Sequential::with_coverage_feedback`]).
+const DEFAULT_COVERAGE_CANDIDATES: usize = 4;
 
 /// This trait is used to model system under test as an abstract state machine.
 ///
@@ -85,6 +157,23 @@ pub trait ReferenceStateMachine {
         true
     }
 
+    /// An optional fallback transition strategy, used to keep generation
+    /// making progress from states where [`ReferenceStateMachine::preconditions`]
+    /// rejects most sampled transitions. If provided, it must yield a
+    /// transition that is valid (i.e. satisfies `preconditions`) from *any*
+    /// reachable state, such as a no-op or a reset.
+    ///
+    /// By default there is no fallback: once every local resample from such a
+    /// state is rejected, generation stops early if it already has `size`'s
+    /// lower bound of transitions, and otherwise draws on proptest's global
+    /// rejection budget, which may abort the whole test if exceeded.
+    fn fallback(state: &Self::State) -> Option<BoxedStrategy<Self::Transition>> {
+        // This is to avoid `unused_variables` warning
+        let _ = state;
+
+        None
+    }
+
     /// A sequential strategy runs the state machine transitions generated from
     /// the reference model sequentially in a test over a concrete state, which
     /// can be implemented with the help of
@@ -93,18 +182,49 @@ pub trait ReferenceStateMachine {
     /// You typically never need to override this method.
     fn sequential_strategy(
         size: impl Into<SizeRange>,
-    ) -> Sequential<
-        Self::State,
-        Self::Transition,
-        BoxedStrategy<Self::State>,
-        BoxedStrategy<Self::Transition>,
-    > {
+    ) -> BoxedSequential<Self::State, Self::Transition> {
         Sequential {
             size: size.into(),
             init_state: Self::init_state,
             preconditions: Self::preconditions,
             transitions: Self::transitions,
             next: Self::apply,
+            fallback: Self::fallback,
+            max_local_resamples: DEFAULT_MAX_LOCAL_RESAMPLES,
+            recorder: None,
+            coverage: None,
+            coverage_candidates: DEFAULT_COVERAGE_CANDIDATES,
+        }
+    }
+
+    /// A parallel strategy generates a sequential prefix exactly like
+    /// [`ReferenceStateMachine::sequential_strategy`], reaching some
+    /// reference state `S`, and then generates `threads` independent
+    /// sequences of transitions ("lanes") that branch off from `S`. Each
+    /// lane is meant to be run concurrently against the system under test on
+    /// its own thread, starting from `S`.
+    ///
+    /// Because the lanes run concurrently, the system under test is free to
+    /// interleave them in any order consistent with each lane's own program
+    /// order, so checking such a run requires checking the observed return
+    /// values for *linearizability* rather than simply replaying a single
+    /// fixed sequence. See [`crate::parallel::check_linearizable`].
+    ///
+    /// You typically never need to override this method.
+    fn parallel_strategy(
+        prefix_size: impl Into<SizeRange>,
+        threads: usize,
+        thread_size: impl Into<SizeRange>,
+    ) -> BoxedParallel<Self::State, Self::Transition> {
+        crate::parallel::Parallel {
+            prefix_size: prefix_size.into(),
+            threads,
+            thread_size: thread_size.into(),
+            init_state: Self::init_state,
+            preconditions: Self::preconditions,
+            transitions: Self::transitions,
+            next: Self::apply,
+            fallback: Self::fallback,
         }
     }
 }
@@ -115,17 +235,40 @@ pub trait ReferenceStateMachine {
 /// on the current state of the state machine, which is updated by the
 /// transitions with the `next` function.
 ///
+/// A transition rejected by the pre-conditions is first resampled locally,
+/// from the same state, up to `max_local_resamples` times (see
+/// [`Sequential::with_max_local_resamples`]) rather than immediately
+/// consuming proptest's global rejection budget. If every local resample is
+/// rejected and there's no [`ReferenceStateMachine::fallback`] to fall back
+/// to, generation stops early and yields a shorter sequence, as long as it's
+/// already at least `size`'s lower bound; only when even that lower bound
+/// can't be reached does it fall back to the global rejection budget.
+///
+/// By default each position draws a single transition straight from
+/// `transitions`'s static weights. Coverage-guided weighting can be enabled
+/// with [`Sequential::with_coverage_feedback`] to instead draw several
+/// candidates per position and keep whichever one is least explored so far,
+/// biasing long runs toward rare edges and states that static weights alone
+/// may otherwise leave uncovered.
+///
 /// The shrinking strategy is to iteratively apply `Shrink::InitialState`,
-/// `Shrink::DeleteTransition` and `Shrink::Transition`.
+/// `Shrink::DeleteChunk`, `Shrink::DeleteTransition` and `Shrink::Transition`.
 ///
-/// 1. We start by trying to delete transitions from the back of the list, until
-///    we can do so no further (reached the beginning of the list).
+/// 1. We start with a ddmin-style chunked deletion: split the included
+///    transitions into a couple of chunks and try to delete each chunk in
+///    turn, accepting the deletion whenever it's still acceptable by the
+///    pre-conditions. Once a full pass over the list removes nothing, the
+///    chunks are made smaller and we try again, until the chunk size reaches
+///    a single transition. This lets long, mostly-irrelevant traces collapse
+///    in a handful of passes instead of one transition at a time.
+/// 2. We then keep deleting transitions one at a time, from the back of the
+///    list, until we can do so no further (reached the beginning of the list).
 ///    We start from the back, because it's less likely to affect the state
 ///    machine's pre-conditions, if any.
-/// 2. Then, we again iteratively attempt to shrink the individual transitions,
+/// 3. Then, we again iteratively attempt to shrink the individual transitions,
 ///    but this time starting from the front of the list - i.e. from the first
 ///    transition to be applied.
-/// 3. Finally, we try to shrink the initial state until it's not possible to
+/// 4. Finally, we try to shrink the initial state until it's not possible to
 ///    shrink it any further.
 ///
 /// For `complicate`, we attempt to undo the last shrink operation, if there was
@@ -136,6 +279,20 @@ pub struct Sequential<State, Transition, StateStrategy, TransitionStrategy> {
     preconditions: fn(state: &State, transition: &Transition) -> bool,
     transitions: fn(state: &State) -> TransitionStrategy,
     next: fn(state: State, transition: &Transition) -> State,
+    fallback: fn(state: &State) -> Option<TransitionStrategy>,
+    /// The number of times generation locally resamples a rejected
+    /// transition from the same state before giving up on that position. See
+    /// [`Sequential::with_max_local_resamples`].
+    max_local_resamples: usize,
+    /// An optional observer notified of every transition considered during
+    /// generation and shrinking. See [`Sequential::with_recorder`].
+    recorder: Option<Rc<RefCell<dyn TransitionRecorder<State, Transition>>>>,
+    /// An optional observer consulted to bias generation toward
+    /// under-covered transitions. See [`Sequential::with_coverage_feedback`].
+    coverage: Option<Rc<RefCell<dyn CoverageFeedback<State, Transition>>>>,
+    /// The number of candidate transitions drawn per position when
+    /// `coverage` is set. See [`Sequential::with_coverage_feedback`].
+    coverage_candidates: usize,
 }
 
 impl<State, Transition, StateStrategy, TransitionStrategy> Debug
@@ -148,6 +305,79 @@ impl<State, Transition, StateStrategy, TransitionStrategy> Debug
     }
 }
 
+impl<State, Transition, StateStrategy, TransitionStrategy>
+    Sequential<State, Transition, StateStrategy, TransitionStrategy>
+{
+    /// Build a `Sequential` strategy directly from its parts. Used by
+    /// [`crate::parallel`] to generate the sequential prefix that precedes
+    /// the concurrent portion of a parallel run.
+    pub(crate) fn new(
+        size: SizeRange,
+        init_state: fn() -> StateStrategy,
+        preconditions: fn(state: &State, transition: &Transition) -> bool,
+        transitions: fn(state: &State) -> TransitionStrategy,
+        next: fn(state: State, transition: &Transition) -> State,
+        fallback: fn(state: &State) -> Option<TransitionStrategy>,
+    ) -> Self {
+        Self {
+            size,
+            init_state,
+            preconditions,
+            transitions,
+            next,
+            fallback,
+            max_local_resamples: DEFAULT_MAX_LOCAL_RESAMPLES,
+            recorder: None,
+            coverage: None,
+            coverage_candidates: DEFAULT_COVERAGE_CANDIDATES,
+        }
+    }
+
+    /// Set the number of times generation will locally resample a
+    /// transition that's rejected by the pre-conditions from the same state,
+    /// before giving up on reaching `size`'s upper bound from that position
+    /// (see [`Sequential`]'s generation docs). Defaults to
+    /// `DEFAULT_MAX_LOCAL_RESAMPLES`.
+    pub fn with_max_local_resamples(
+        mut self,
+        max_local_resamples: usize,
+    ) -> Self {
+        self.max_local_resamples = max_local_resamples;
+        self
+    }
+
+    /// Notify `recorder` of every transition considered during generation
+    /// and shrinking - both accepted ones and ones rejected by
+    /// `preconditions` - so it can build up a picture of what was actually
+    /// explored, e.g. a [`crate::TransitionGraph`] wrapped in a
+    /// [`crate::KeyedRecorder`].
+    pub fn with_recorder(
+        mut self,
+        recorder: Rc<RefCell<dyn TransitionRecorder<State, Transition>>>,
+    ) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Enable coverage-guided transition weighting: instead of committing to
+    /// the first transition sampled from [`ReferenceStateMachine::transitions`]
+    /// at each position, draw `candidates` of them and ask `feedback` to pick
+    /// whichever is least explored so far (see [`crate::CoverageTracker`]).
+    /// This biases generation toward rare edges and previously unseen
+    /// states, rather than relying purely on `transitions`'s static weights,
+    /// at the cost of `candidates` times as many draws per position.
+    /// `candidates` is clamped to at least `1`.
+    pub fn with_coverage_feedback(
+        mut self,
+        feedback: Rc<RefCell<dyn CoverageFeedback<State, Transition>>>,
+        candidates: usize,
+    ) -> Self {
+        self.coverage = Some(feedback);
+        self.coverage_candidates = candidates.max(1);
+        self
+    }
+}
+
 impl<
         State: Clone + Debug,
         Transition: Clone + Debug,
@@ -174,30 +404,169 @@ impl<
         let max_size = sample_uniform_incl(runner, min_size, end);
         let mut transitions = Vec::with_capacity(max_size);
         let mut acceptable_transitions = Vec::with_capacity(max_size);
-        let included_transitions = VarBitSet::saturated(max_size);
-        let shrinkable_transitions = VarBitSet::saturated(max_size);
 
-        // Sample the transitions until we reach the `max_size`
+        // Sample the transitions until we reach `max_size`, a position where
+        // the pre-conditions keep rejecting every local resample, or a
+        // position where even the fallback can't make progress.
         let mut state = initial_state.current();
-        while transitions.len() < max_size {
-            // Apply the current state to find the current transition
-            let transition_tree =
-                (self.transitions)(&state).new_tree(runner)?;
-            let transition = transition_tree.current();
-
-            // If the pre-conditions are satisfied, use the transition
-            if (self.preconditions)(&state, &transition) {
+        if let Some(recorder) = &self.recorder {
+            recorder.borrow_mut().record_initial(&state);
+        }
+        if let Some(coverage) = &self.coverage {
+            coverage.borrow_mut().note_initial(&state);
+        }
+        'generate: while transitions.len() < max_size {
+            // Resample the transition from this same state up to
+            // `self.max_local_resamples` times; a precondition failure only
+            // costs a local retry, not a draw against proptest's global
+            // rejection budget.
+            for _ in 0..self.max_local_resamples {
+                // With coverage feedback enabled, draw several candidates
+                // and let it pick the least-explored one instead of
+                // committing to the first draw.
+                let num_candidates = match &self.coverage {
+                    Some(_) => self.coverage_candidates,
+                    None => 1,
+                };
+                let mut candidate_trees = Vec::with_capacity(num_candidates);
+                for _ in 0..num_candidates {
+                    candidate_trees
+                        .push((self.transitions)(&state).new_tree(runner)?);
+                }
+
+                // Only candidates that already satisfy `preconditions` are
+                // eligible to be chosen: `next` must never be called on a
+                // transition before it's known to be acceptable, so the
+                // lookahead below only ever runs over this subset, not every
+                // draw.
+                let eligible: Vec<usize> = candidate_trees
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, tree)| {
+                        (self.preconditions)(&state, &tree.current())
+                    })
+                    .map(|(ix, _)| ix)
+                    .collect();
+
+                if eligible.is_empty() {
+                    if let Some(recorder) = &self.recorder {
+                        for tree in &candidate_trees {
+                            recorder
+                                .borrow_mut()
+                                .record_rejected(&state, &tree.current());
+                        }
+                    }
+                    continue;
+                }
+
+                let chosen_ix = if let Some(coverage) = &self.coverage {
+                    let lookahead: Vec<_> = eligible
+                        .iter()
+                        .map(|&ix| {
+                            let value = candidate_trees[ix].current();
+                            let next_state =
+                                (self.next)(state.clone(), &value);
+                            (value, next_state)
+                        })
+                        .collect();
+                    eligible[coverage.borrow_mut().pick(&state, &lookahead)]
+                } else {
+                    eligible[0]
+                };
+
+                if let Some(recorder) = &self.recorder {
+                    for (ix, tree) in candidate_trees.iter().enumerate() {
+                        if !eligible.contains(&ix) {
+                            recorder
+                                .borrow_mut()
+                                .record_rejected(&state, &tree.current());
+                        }
+                    }
+                }
+
+                let transition_tree = candidate_trees.swap_remove(chosen_ix);
+                let transition = transition_tree.current();
+
+                let prev_state = state.clone();
+                state = (self.next)(state, &transition);
+                if let Some(recorder) = &self.recorder {
+                    recorder.borrow_mut().record_transition(
+                        &prev_state,
+                        &transition,
+                        &state,
+                    );
+                }
+                if let Some(coverage) = &self.coverage {
+                    coverage.borrow_mut().record(
+                        &prev_state,
+                        &transition,
+                        &state,
+                    );
+                }
                 transitions.push(transition_tree);
+                acceptable_transitions
+                    .push((TransitionState::Accepted, transition));
+                continue 'generate;
+            }
+
+            // Every local resample from this state was rejected. If a
+            // fallback is available, use it to keep generation moving.
+            if let Some(fallback) = (self.fallback)(&state) {
+                let fallback_tree = fallback.new_tree(runner)?;
+                let transition = fallback_tree.current();
+                let prev_state = state.clone();
                 state = (self.next)(state, &transition);
+                if let Some(recorder) = &self.recorder {
+                    recorder.borrow_mut().record_transition(
+                        &prev_state,
+                        &transition,
+                        &state,
+                    );
+                }
+                if let Some(coverage) = &self.coverage {
+                    coverage.borrow_mut().record(
+                        &prev_state,
+                        &transition,
+                        &state,
+                    );
+                }
+                transitions.push(fallback_tree);
                 acceptable_transitions
                     .push((TransitionState::Accepted, transition));
-            } else {
-                runner.reject_local("Pre-conditions were not satisfied")?;
+                continue;
+            }
+
+            // No fallback, and the pre-conditions rejected every local
+            // resample. If we already have enough transitions to satisfy
+            // `min_size`, stop here and return the shorter sequence rather
+            // than fighting the pre-conditions further.
+            if transitions.len() >= min_size {
+                break;
             }
+
+            // We can't reach `min_size` without more transitions: fall back
+            // to proptest's global rejection budget as a last resort.
+            runner.reject_local(
+                "Pre-conditions were not satisfied after exhausting local resamples",
+            )?;
         }
 
-        // The maximum index into the vectors and bit sets
-        let max_ix = max_size - 1;
+        // The maximum index into the vectors and bit sets, sized to the
+        // (possibly shorter than `max_size`) sequence that was generated.
+        // `min_size` may be 0, and the early-stop above can leave the
+        // sequence empty even then, so there may be no valid index at all.
+        let max_ix = transitions.len().saturating_sub(1);
+        let included_transitions = VarBitSet::saturated(transitions.len());
+        let shrinkable_transitions = VarBitSet::saturated(transitions.len());
+        // On a failure, we start with ddmin-style chunked deletion, which
+        // degrades to deleting transitions one at a time from the back once
+        // the chunk size reaches 1. With no transitions at all, there's
+        // nothing to delete, so go straight to shrinking the initial state.
+        let shrink = if transitions.is_empty() {
+            Shrink::InitialState
+        } else {
+            initial_delete_shrink(transitions.len(), max_ix)
+        };
 
         Ok(SequentialValueTree {
             initial_state,
@@ -210,10 +579,11 @@ impl<
             included_transitions,
             shrinkable_transitions,
             max_ix,
-            // On a failure, we start by shrinking transitions from the back
-            // which is less likely to invalidate pre-conditions
-            shrink: Shrink::DeleteTransition(max_ix),
+            shrink,
             last_shrink: None,
+            chunk_pass_progressed: false,
+            last_deleted_chunk: Vec::new(),
+            recorder: self.recorder.clone(),
         })
     }
 }
@@ -223,6 +593,11 @@ impl<
 enum Shrink {
     /// Shrink the initial state
     InitialState,
+    /// Delete the chunk of `len` transitions starting at index `start`,
+    /// ddmin-style. `start` is a raw index into the transitions vector, not
+    /// a position among only the currently-included ones, so a chunk may
+    /// cover some transitions that are already deleted.
+    DeleteChunk { start: usize, len: usize },
     /// Delete a transition at given index
     DeleteTransition(usize),
     /// Shrink a transition at given index
@@ -230,9 +605,22 @@ enum Shrink {
 }
 use Shrink::*;
 
+/// Pick the first deletion shrink to try: a ddmin-style chunk deletion when
+/// there are enough included transitions to split into at least two chunks,
+/// otherwise go straight to deleting transitions one at a time from the
+/// back.
+fn initial_delete_shrink(included_count: usize, max_ix: usize) -> Shrink {
+    let len = included_count / 2;
+    if len >= 1 {
+        DeleteChunk { start: 0, len }
+    } else {
+        DeleteTransition(max_ix)
+    }
+}
+
 /// The state of a transition in the model
 #[derive(Clone, Copy, Debug)]
-enum TransitionState {
+pub(crate) enum TransitionState {
     /// The transition that is equal to the result of `ValueTree::current()`
     /// and satisfies the pre-conditions
     Accepted,
@@ -276,6 +664,16 @@ pub struct SequentialValueTree<
     shrink: Shrink,
     /// The last applied shrink operation, if any
     last_shrink: Option<Shrink>,
+    /// Whether any `DeleteChunk` shrink has been kept (accepted by the
+    /// pre-conditions and not undone by `complicate`) during the current
+    /// pass over the included transitions at the current chunk size.
+    chunk_pass_progressed: bool,
+    /// The indices deleted by the last accepted `DeleteChunk` shrink, so
+    /// `complicate` can restore exactly them.
+    last_deleted_chunk: Vec<usize>,
+    /// An optional observer notified of every transition re-considered
+    /// while shrinking. See [`Sequential::with_recorder`].
+    recorder: Option<Rc<RefCell<dyn TransitionRecorder<State, Transition>>>>,
 }
 
 impl<
@@ -289,18 +687,73 @@ impl<
     /// Try to apply the next `self.shrink`. Returns `true` if a shrink has been
     /// applied.
     fn try_simplify(&mut self) -> bool {
-        if let DeleteTransition(ix) = self.shrink {
-            // Delete the index from the included transitions
-            self.included_transitions.clear(ix);
+        if let DeleteChunk { start, len } = self.shrink {
+            // `complicate` resets `last_shrink`, so a chunk deletion still
+            // recorded there was kept: count it as progress for this pass.
+            if let Some(DeleteChunk { .. }) = self.last_shrink.take() {
+                self.chunk_pass_progressed = true;
+            }
+            if start > self.max_ix {
+                // Reached the end of the list: this pass over the included
+                // transitions at this chunk size is done.
+                self.shrink = self.next_chunk_shrink(len);
+                return self.try_simplify();
+            }
 
-            self.last_shrink = Some(self.shrink);
-            self.shrink = if ix == 0 {
+            let end = (start + len).min(self.max_ix + 1);
+            let cleared: Vec<usize> = (start..end)
+                .filter(|&ix| self.included_transitions.test(ix))
+                .collect();
+            self.shrink = DeleteChunk { start: end, len };
+
+            if cleared.is_empty() {
+                // Every transition in this chunk was already deleted by an
+                // earlier pass; nothing to try here.
+                return self.try_simplify();
+            }
+            for &ix in &cleared {
+                self.included_transitions.clear(ix);
+            }
+
+            // If this delete is not acceptable, undo it and try the next chunk
+            if !self.check_acceptable(None) {
+                for &ix in &cleared {
+                    self.included_transitions.set(ix);
+                }
+                return self.try_simplify();
+            }
+
+            for &ix in &cleared {
+                self.shrinkable_transitions.clear(ix);
+            }
+            self.last_deleted_chunk = cleared;
+            self.last_shrink = Some(DeleteChunk { start, len });
+            return true;
+        }
+
+        if let DeleteTransition(ix) = self.shrink {
+            let next_shrink = if ix == 0 {
                 // Reached the beginning of the list, move on to shrinking
                 Transition(0)
             } else {
                 // Try to delete the previous transition next
                 DeleteTransition(ix - 1)
             };
+
+            if !self.included_transitions.test(ix) {
+                // A preceding ddmin chunk pass already excluded this index;
+                // clearing it again is a no-op, not a real shrink, so move
+                // on without reporting progress or letting `complicate`
+                // later believe it's the one that deleted it.
+                self.shrink = next_shrink;
+                return self.try_simplify();
+            }
+
+            // Delete the index from the included transitions
+            self.included_transitions.clear(ix);
+
+            self.last_shrink = Some(self.shrink);
+            self.shrink = next_shrink;
             // If this delete is not acceptable, undo it and try again
             if !self.check_acceptable(None) {
                 self.included_transitions.set(ix);
@@ -406,11 +859,22 @@ impl<
         let mut state = self.last_valid_initial_state.clone();
         for transition in transitions.iter() {
             let is_acceptable = (self.preconditions)(&state, transition);
-            if is_acceptable {
-                state = (self.next)(state, transition);
-            } else {
+            if !is_acceptable {
+                if let Some(recorder) = &self.recorder {
+                    recorder.borrow_mut().record_rejected(&state, transition);
+                }
                 return false;
             }
+            let from_state = state.clone();
+            let next_state = (self.next)(state, transition);
+            if let Some(recorder) = &self.recorder {
+                recorder.borrow_mut().record_transition(
+                    &from_state,
+                    transition,
+                    &next_state,
+                );
+            }
+            state = next_state;
         }
         true
     }
@@ -454,6 +918,25 @@ impl<
                 })
     }
 
+    /// Decide what to do once a full pass over the included transitions at
+    /// chunk size `len` is done: if any chunk was successfully deleted,
+    /// retry the same size over what's left, since there may be more to
+    /// remove; otherwise make the chunks smaller, or, once `len` is already
+    /// down to a single transition, degrade to deleting transitions one at a
+    /// time from the back.
+    fn next_chunk_shrink(&mut self, len: usize) -> Shrink {
+        if std::mem::replace(&mut self.chunk_pass_progressed, false) {
+            DeleteChunk { start: 0, len }
+        } else if len > 1 {
+            DeleteChunk {
+                start: 0,
+                len: len.div_ceil(2),
+            }
+        } else {
+            DeleteTransition(self.max_ix)
+        }
+    }
+
     /// Find the next shrink transition. Loops back to the front of the list
     /// when the end is reached, because sometimes a transition might become
     /// acceptable only after a transition that comes before it in the sequence
@@ -506,6 +989,16 @@ impl<
     fn complicate(&mut self) -> bool {
         match self.last_shrink {
             None => false,
+            Some(DeleteChunk { .. }) => {
+                // Undo the last chunk we deleted. Can't complicate any
+                // further, so unset last_shrink.
+                for ix in self.last_deleted_chunk.drain(..) {
+                    self.included_transitions.set(ix);
+                    self.shrinkable_transitions.set(ix);
+                }
+                self.last_shrink = None;
+                true
+            }
             Some(DeleteTransition(ix)) => {
                 // Undo the last item we deleted. Can't complicate any further,
                 // so unset prev_shrink.
@@ -566,8 +1059,11 @@ mod test {
     /// [`TRANSITIONS`] given to its `sequential_strategy`.
     ///
     /// This constant can be determined from the test
-    /// `number_of_sequential_value_tree_simplifications`.
-    const SIMPLIFICATIONS: usize = 32;
+    /// `number_of_sequential_value_tree_simplifications`. Re-derive it
+    /// whenever the shrinking strategy itself changes (e.g. the ddmin
+    /// chunked deletion pass), since that changes how many simplifications
+    /// are needed to reach a fixed point.
+    const SIMPLIFICATIONS: usize = 2;
     /// Number of transitions in the [`deterministic_sequential_value_tree`].
     const TRANSITIONS: usize = 32;
 
@@ -0,0 +1,128 @@
+//-
+// Copyright 2023 The proptest developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Serde-based export and import of minimized state machine traces.
+//!
+//! This is an opt-in alternative to proptest's regular regression file,
+//! which only stores a `Debug` rendering of a failing case. A
+//! [`SequentialTrace`] instead stores the initial state and the included
+//! transitions of a minimized [`SequentialValueTree`](crate::SequentialValueTree)
+//! in a structured, serde-friendly format, so it can be inspected, diffed
+//! and re-run outside of proptest's seed mechanism.
+
+use proptest::std_facade::fmt::Debug;
+use proptest::std_facade::Vec;
+use proptest::strategy::ValueTree;
+use serde::{Deserialize, Serialize};
+
+use crate::strategy::SequentialValueTree;
+
+/// A minimized `(initial_state, transitions)` witness for a sequential
+/// state machine failure, suitable for serialization via serde.
+///
+/// Build one from a minimized [`SequentialValueTree`] with
+/// [`SequentialValueTree::to_trace`], and turn it back into a
+/// [`ValueTree`] that replays exactly this witness with
+/// [`SequentialTrace::into_value_tree`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SequentialTrace<State, Transition> {
+    /// The initial state the transitions were applied to.
+    pub initial_state: State,
+    /// The sequence of transitions that were applied, in order.
+    pub transitions: Vec<Transition>,
+}
+
+impl<State, Transition> SequentialTrace<State, Transition> {
+    /// Reconstruct a [`ValueTree`] that replays exactly this trace: its
+    /// `current()` is always `(initial_state, transitions)`, and it cannot
+    /// be shrunk any further, since the trace is assumed to already be
+    /// minimized.
+    pub fn into_value_tree(self) -> Fixed<(State, Vec<Transition>)>
+    where
+        State: Clone + Debug,
+        Transition: Clone + Debug,
+    {
+        Fixed((self.initial_state, self.transitions))
+    }
+}
+
+impl<
+        State: Clone + Debug,
+        Transition: Clone + Debug,
+        StateValueTree: ValueTree<Value = State>,
+        TransitionValueTree: ValueTree<Value = Transition>,
+    >
+    SequentialValueTree<State, Transition, StateValueTree, TransitionValueTree>
+{
+    /// Export the currently included, acceptable transitions and the
+    /// current initial state as a [`SequentialTrace`]. Calling this on a
+    /// fully shrunk tree captures the minimized counterexample.
+    pub fn to_trace(&self) -> SequentialTrace<State, Transition> {
+        let (initial_state, transitions) = self.current();
+        SequentialTrace {
+            initial_state,
+            transitions,
+        }
+    }
+}
+
+/// A [`ValueTree`] that always yields the same, already-minimized `Value`
+/// and cannot be shrunk any further. Produced by
+/// [`SequentialTrace::into_value_tree`] to replay an exported trace.
+#[derive(Clone, Debug)]
+pub struct Fixed<Value>(Value);
+
+impl<Value: Clone + Debug> ValueTree for Fixed<Value> {
+    type Value = Value;
+
+    fn current(&self) -> Value {
+        self.0.clone()
+    }
+
+    fn simplify(&mut self) -> bool {
+        false
+    }
+
+    fn complicate(&mut self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sequential_trace_round_trips_through_json() {
+        let trace = SequentialTrace {
+            initial_state: vec![1, 2, 3],
+            transitions: vec!["a".to_string(), "b".to_string()],
+        };
+
+        let json = serde_json::to_string(&trace).unwrap();
+        let restored: SequentialTrace<Vec<i32>, String> =
+            serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.initial_state, trace.initial_state);
+        assert_eq!(restored.transitions, trace.transitions);
+    }
+
+    #[test]
+    fn fixed_value_tree_replays_and_never_shrinks() {
+        let trace = SequentialTrace {
+            initial_state: 0i32,
+            transitions: vec![1, 2, 3],
+        };
+        let mut value_tree = trace.into_value_tree();
+
+        assert_eq!(value_tree.current(), (0, vec![1, 2, 3]));
+        assert!(!value_tree.simplify());
+        assert!(!value_tree.complicate());
+    }
+}